@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::paths::expand_tilde;
+use crate::repo::Package;
+
+fn cache_path() -> Result<PathBuf> {
+    expand_tilde("~/.config/rust-solv/cache.sqlite3")
+}
+
+/// Opens (creating if needed) the local SQLite cache of parsed repo metadata.
+pub fn open() -> Result<Connection> {
+    let path = cache_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create cache directory {:?}", parent))?;
+    }
+    let conn = Connection::open(&path)
+        .with_context(|| format!("Failed to open the package metadata cache at {:?}", path))?;
+    // Concurrent repo fetches each open their own connection to this same file; rather
+    // than failing immediately with "database is locked", let SQLite retry a writer that
+    // finds the database busy for up to this long before giving up.
+    conn.busy_timeout(Duration::from_secs(30))
+        .with_context(|| "Failed to set the cache connection's busy timeout")?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS packages (
+            repo_name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            packages TEXT NOT NULL,
+            PRIMARY KEY (repo_name, checksum)
+        )",
+        [],
+    )
+    .with_context(|| "Failed to initialize the package metadata cache schema")?;
+    Ok(conn)
+}
+
+/// Looks up the packages cached for `repo_name` under `checksum` (the `primary` data's
+/// checksum from `repomd.xml`), returning `None` on a cache miss.
+pub fn get(conn: &Connection, repo_name: &str, checksum: &str) -> Result<Option<Vec<Package>>> {
+    let mut stmt =
+        conn.prepare("SELECT packages FROM packages WHERE repo_name = ?1 AND checksum = ?2")?;
+    let mut rows = stmt.query(params![repo_name, checksum])?;
+    let Some(row) = rows.next()? else {
+        return Ok(None);
+    };
+    let packages_json: String = row.get(0)?;
+    let packages = serde_json::from_str(&packages_json)
+        .with_context(|| "Failed to deserialize cached packages")?;
+    Ok(Some(packages))
+}
+
+/// Upserts `packages` into the cache under `repo_name`/`checksum`.
+pub fn put(conn: &Connection, repo_name: &str, checksum: &str, packages: &[Package]) -> Result<()> {
+    let packages_json =
+        serde_json::to_string(packages).with_context(|| "Failed to serialize packages for caching")?;
+    conn.execute(
+        "INSERT OR REPLACE INTO packages (repo_name, checksum, packages) VALUES (?1, ?2, ?3)",
+        params![repo_name, checksum, packages_json],
+    )
+    .with_context(|| "Failed to write packages to the cache")?;
+    Ok(())
+}
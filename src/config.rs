@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::paths::expand_tilde;
+use crate::repo::resolve_mirrors;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    #[serde(rename = "repo", default)]
+    repos: Vec<RepoConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoConfig {
+    name: String,
+    baseurl: Option<String>,
+    mirrorlist: Option<String>,
+    metalink: Option<String>,
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Config> {
+        let path = match path.to_str() {
+            Some(path) => expand_tilde(path)?,
+            None => path.to_path_buf(),
+        };
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {:?}", path))?;
+        toml::from_str(&contents).with_context(|| format!("Failed to parse config file {:?}", path))
+    }
+
+    /// The `(name, mirrors)` of every configured repo, with `mirrorlist`/`metalink`
+    /// entries already resolved to their candidate baseurls.
+    pub fn get_repos(&self) -> Result<Vec<(String, Vec<String>)>> {
+        self.repos
+            .iter()
+            .map(|repo| {
+                let mirrors = resolve_mirrors(
+                    repo.baseurl.as_deref(),
+                    repo.mirrorlist.as_deref(),
+                    repo.metalink.as_deref(),
+                )
+                .with_context(|| format!("Repo {:?} has no usable mirrors", repo.name))?;
+                Ok((repo.name.clone(), mirrors))
+            })
+            .collect()
+    }
+}
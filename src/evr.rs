@@ -0,0 +1,212 @@
+//! RPM epoch/version/release comparison.
+//!
+//! This reimplements `rpmvercmp`, the algorithm `rpm`/`dnf` use to decide
+//! whether one version string is newer, older or equal to another, so that
+//! a `Requires` entry carrying a version flag (`EQ`, `GE`, `GT`, `LE`, `LT`)
+//! can be matched against a candidate `Provides` entry.
+
+use std::cmp::Ordering;
+
+/// An epoch/version/release triple, as carried by a `Provides`/`Requires` `Entry`.
+#[derive(Debug, Clone, Copy)]
+pub struct Evr<'a> {
+    pub epoch: &'a str,
+    pub ver: &'a str,
+    pub rel: &'a str,
+}
+
+/// Compares two version (or release) strings the way RPM's `rpmvercmp` does.
+pub fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (mut one, mut two) = (0usize, 0usize);
+
+    loop {
+        while one < a.len() && !a[one].is_ascii_alphanumeric() && a[one] != '~' && a[one] != '^' {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() && b[two] != '~' && b[two] != '^' {
+            two += 1;
+        }
+
+        // A `~` always sorts older than anything, including the end of the string.
+        if matches!(a.get(one), Some('~')) || matches!(b.get(two), Some('~')) {
+            if !matches!(a.get(one), Some('~')) {
+                return Ordering::Greater;
+            }
+            if !matches!(b.get(two), Some('~')) {
+                return Ordering::Less;
+            }
+            one += 1;
+            two += 1;
+            continue;
+        }
+
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+
+        // A trailing `^` sorts newer than the empty string, but older than anything else.
+        if a[one] == '^' || b[two] == '^' {
+            if a[one] != '^' {
+                return Ordering::Greater;
+            }
+            if b[two] != '^' {
+                return Ordering::Less;
+            }
+            one += 1;
+            two += 1;
+            continue;
+        }
+
+        let (start_one, start_two) = (one, two);
+        let is_num = a[one].is_ascii_digit();
+        if is_num {
+            while one < a.len() && a[one].is_ascii_digit() {
+                one += 1;
+            }
+            while two < b.len() && b[two].is_ascii_digit() {
+                two += 1;
+            }
+        } else {
+            while one < a.len() && a[one].is_ascii_alphabetic() {
+                one += 1;
+            }
+            while two < b.len() && b[two].is_ascii_alphabetic() {
+                two += 1;
+            }
+        }
+
+        // The two sides disagree on the run's class (digit vs. alpha): a digit run
+        // always outranks an alpha run.
+        if start_two == two {
+            return if is_num { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let one_run = &a[start_one..one];
+        let two_run = &b[start_two..two];
+
+        let ord = if is_num {
+            trim_leading_zeros(one_run)
+                .len()
+                .cmp(&trim_leading_zeros(two_run).len())
+                .then_with(|| trim_leading_zeros(one_run).cmp(trim_leading_zeros(two_run)))
+        } else {
+            one_run.cmp(two_run)
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    match (one >= a.len(), two >= b.len()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+fn trim_leading_zeros(run: &[char]) -> &[char] {
+    let first_nonzero = run.iter().position(|&c| c != '0').unwrap_or(run.len() - 1);
+    &run[first_nonzero..]
+}
+
+fn compare_epoch(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => rpmvercmp(a, b),
+    }
+}
+
+/// Full EVR compare: epoch first (numeric, absent treated as `"0"`), then `ver`, then `rel`.
+pub fn evr_compare(provided: &Evr, required: &Evr) -> Ordering {
+    compare_epoch(provided.epoch, required.epoch)
+        .then_with(|| rpmvercmp(provided.ver, required.ver))
+        .then_with(|| rpmvercmp(provided.rel, required.rel))
+}
+
+/// Returns whether `provided_evr` satisfies a `Requires` entry carrying `flag`
+/// (`EQ`, `GE`, `GT`, `LE` or `LT`) against `required_evr`. An unrecognized flag
+/// imposes no version constraint.
+pub fn evr_satisfies(provided_evr: &Evr, flag: &str, required_evr: &Evr) -> bool {
+    let ord = evr_compare(provided_evr, required_evr);
+    match flag {
+        "EQ" => ord == Ordering::Equal,
+        "GE" => ord != Ordering::Less,
+        "GT" => ord == Ordering::Greater,
+        "LE" => ord != Ordering::Greater,
+        "LT" => ord == Ordering::Less,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evr<'a>(epoch: &'a str, ver: &'a str, rel: &'a str) -> Evr<'a> {
+        Evr { epoch, ver, rel }
+    }
+
+    #[test]
+    fn test_rpmvercmp() {
+        let cases = [
+            ("1.0", "1.0", Ordering::Equal),
+            ("1.0", "2.0", Ordering::Less),
+            ("2.0", "1.0", Ordering::Greater),
+            ("1.0~beta", "1.0", Ordering::Less),
+            ("1.0", "1.0~beta", Ordering::Greater),
+            ("1.0~beta", "1.0~beta", Ordering::Equal),
+            ("1.0~~", "1.0~beta", Ordering::Less),
+            ("1.0^", "1.0", Ordering::Greater),
+            ("1.0", "1.0^", Ordering::Less),
+            ("007", "7", Ordering::Equal),
+            ("007", "07", Ordering::Equal),
+            ("10", "9", Ordering::Greater),
+            ("1.a", "1.1", Ordering::Less),
+            ("1.1", "1.a", Ordering::Greater),
+            ("1.0.0", "1.0", Ordering::Greater),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(rpmvercmp(a, b), expected, "rpmvercmp({:?}, {:?})", a, b);
+        }
+    }
+
+    #[test]
+    fn test_evr_compare() {
+        assert_eq!(
+            evr_compare(&evr("1", "1.0", "1"), &evr("0", "9.0", "1")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            evr_compare(&evr("0", "1.0", "1"), &evr("0", "1.0", "2")),
+            Ordering::Less
+        );
+        assert_eq!(
+            evr_compare(&evr("0", "1.0", "1"), &evr("0", "1.0", "1")),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn test_evr_satisfies() {
+        let provided = evr("0", "1.0", "1");
+        assert!(evr_satisfies(&provided, "EQ", &evr("0", "1.0", "1")));
+        assert!(!evr_satisfies(&provided, "EQ", &evr("0", "1.1", "1")));
+        assert!(evr_satisfies(&provided, "GE", &evr("0", "1.0", "1")));
+        assert!(evr_satisfies(&provided, "GE", &evr("0", "0.9", "1")));
+        assert!(!evr_satisfies(&provided, "GE", &evr("0", "1.1", "1")));
+        assert!(evr_satisfies(&provided, "GT", &evr("0", "0.9", "1")));
+        assert!(!evr_satisfies(&provided, "GT", &evr("0", "1.0", "1")));
+        assert!(evr_satisfies(&provided, "LE", &evr("0", "1.0", "1")));
+        assert!(evr_satisfies(&provided, "LE", &evr("0", "1.1", "1")));
+        assert!(!evr_satisfies(&provided, "LE", &evr("0", "0.9", "1")));
+        assert!(evr_satisfies(&provided, "LT", &evr("0", "1.1", "1")));
+        assert!(!evr_satisfies(&provided, "LT", &evr("0", "1.0", "1")));
+        assert!(evr_satisfies(&provided, "UNKNOWN", &evr("0", "99.0", "1")));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod cache;
+pub mod config;
+pub mod evr;
+mod paths;
+pub mod repo;
+pub mod solve;
@@ -2,7 +2,8 @@ use anyhow::Result;
 use rust_solv::{config, repo, solve};
 use std::{env, path::Path};
 
-fn main() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<()> {
     let packages: Vec<String> = env::args()
         .enumerate()
         .filter(|&(i, _)| i > 0)
@@ -12,18 +13,38 @@ fn main() -> Result<()> {
         panic!("Package name not found!");
     } else {
         let cfg = config::Config::from_file(Path::new("~/.config/rust-solv/config.toml"))?;
-        if let Some(repo_baseurl) = cfg.get_repo_baseurl() {
-            let repo = repo::Repo::from_baseurl(repo_baseurl)?;
-            for package_name in packages {
-                match solve::check_package_satisfiability_in_repo(&repo, &package_name) {
-                    Ok(true) => println!("Congratulations! Package {}'s dependencies can be satisfied in the repo. :)", package_name),
-                    Ok(false) => println!("Sorry, package {}'s dependencies can not be satisfied in the repo. :(", package_name),
-                    Err(_) => println!("Error: something wrong happened while solving the dependency problem of package {}.", package_name),
+        let repos = cfg.get_repos()?;
+        if repos.is_empty() {
+            panic!("No repo baseurls found! Please check the config file!");
+        }
+        let fetched: Vec<repo::Repo> = repo::Repo::from_many_baseurls(repos)
+            .await
+            .into_iter()
+            .filter_map(|result| match result {
+                Ok(repo) => Some(repo),
+                Err(err) => {
+                    eprintln!("Warning: failed to fetch a repo: {}", err);
+                    None
+                }
+            })
+            .collect();
+        anyhow::ensure!(!fetched.is_empty(), "No repo could be fetched!");
+        let merged = repo::Repo::merge(fetched);
+        match solve::resolve(&merged, &packages) {
+            Ok(transaction) => {
+                println!("The following packages will be installed:");
+                for package in transaction {
+                    println!(
+                        "  {}-{}-{}",
+                        package.name, package.version.ver, package.version.rel
+                    );
                 }
             }
-            Ok(())
-        } else {
-            panic!("Repo baseurl not found! Please check the config file!");
+            Err(err) => println!(
+                "Sorry, the requested packages' dependencies can not be satisfied: {}",
+                err
+            ),
         }
+        Ok(())
     }
-}
\ No newline at end of file
+}
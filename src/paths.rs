@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+/// Expands a leading `~` in `path` to the user's home directory. Unlike a shell, neither
+/// `fs::read_to_string` nor SQLite expands `~` on its own, so paths read from source (like
+/// our hardcoded config/cache locations) need this before they're used.
+pub(crate) fn expand_tilde(path: &str) -> Result<PathBuf> {
+    match path.strip_prefix('~') {
+        Some(rest) => {
+            let home = dirs::home_dir().with_context(|| "Could not determine home directory")?;
+            Ok(home.join(rest.trim_start_matches('/')))
+        }
+        None => Ok(PathBuf::from(path)),
+    }
+}
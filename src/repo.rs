@@ -5,72 +5,79 @@ use std::process::Command;
 use anyhow::{Context, Result};
 use configparser;
 use flate2::read::GzDecoder;
+use futures;
 use quick_xml;
 use reqwest;
 use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use tokio::task;
+use xz2::read::XzDecoder;
+
+use crate::cache;
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Version {
-    epoch: String,
-    ver: String,
-    rel: String,
+pub struct Version {
+    pub epoch: String,
+    pub ver: String,
+    pub rel: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Entry {
-    name: String,
-    flags: Option<String>,
-    epoch: Option<String>,
-    ver: Option<String>,
-    rel: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub flags: Option<String>,
+    pub epoch: Option<String>,
+    pub ver: Option<String>,
+    pub rel: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Provides {
+pub struct Provides {
     #[serde(rename = "entry")]
-    entries: Vec<Entry>,
+    pub entries: Vec<Entry>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Requires {
+pub struct Requires {
     #[serde(rename = "entry")]
-    entries: Vec<Entry>,
+    pub entries: Vec<Entry>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Conflicts {
+pub struct Conflicts {
     #[serde(rename = "entry")]
-    entries: Vec<Entry>,
+    pub entries: Vec<Entry>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Obsoletes {
+pub struct Obsoletes {
     #[serde(rename = "entry")]
-    entries: Vec<Entry>,
+    pub entries: Vec<Entry>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Format {
-    provides: Option<Provides>,
-    requires: Option<Requires>,
-    conflicts: Option<Conflicts>,
-    obsoletes: Option<Obsoletes>,
+pub struct Format {
+    pub provides: Option<Provides>,
+    pub requires: Option<Requires>,
+    pub conflicts: Option<Conflicts>,
+    pub obsoletes: Option<Obsoletes>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Package {
-    r#type: String,
-    name: String,
-    version: Version,
-    format: Format,
+pub struct Package {
+    pub r#type: String,
+    pub name: String,
+    pub version: Version,
+    pub format: Format,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Repo {
+pub struct Repo {
     #[serde(rename = "package")]
-    packages: Vec<Package>,
+    pub packages: Vec<Package>,
     #[serde(skip)]
-    name: String,
+    pub name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -82,16 +89,133 @@ struct Repomd {
 #[derive(Debug, Serialize, Deserialize)]
 struct Data {
     r#type: String,
+    checksum: Checksum,
     location: Location,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct Checksum {
+    r#type: String,
+    #[serde(rename = "$value")]
+    value: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Location {
     href: String,
 }
 
+/// Compression formats the `primary` data file may be shipped in.
+#[derive(Debug, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression of `bytes`, downloaded from a location whose href is
+    /// `href`: first from the file extension, falling back to the file's magic bytes.
+    fn detect(href: &str, bytes: &[u8]) -> Compression {
+        if href.ends_with(".zst") {
+            return Compression::Zstd;
+        }
+        if href.ends_with(".xz") {
+            return Compression::Xz;
+        }
+        if href.ends_with(".gz") {
+            return Compression::Gzip;
+        }
+        if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if bytes.starts_with(&[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00]) {
+            Compression::Xz
+        } else {
+            Compression::Gzip
+        }
+    }
+}
+
+/// Decompresses `bytes` as `compression` into a UTF-8 string.
+fn decompress(compression: Compression, bytes: &[u8]) -> Result<String> {
+    let mut text = String::new();
+    match compression {
+        Compression::Gzip => {
+            GzDecoder::new(bytes).read_to_string(&mut text)?;
+        }
+        Compression::Xz => {
+            XzDecoder::new(bytes).read_to_string(&mut text)?;
+        }
+        Compression::Zstd => {
+            zstd::stream::read::Decoder::new(bytes)?.read_to_string(&mut text)?;
+        }
+    }
+    Ok(text)
+}
+
+/// Computes the hex digest of `bytes` using the algorithm named by a repomd.xml
+/// `<checksum type="...">` attribute (e.g. `sha256`, `sha1`, `md5`).
+fn checksum_digest(algorithm: &str, bytes: &[u8]) -> Result<String> {
+    match algorithm.to_ascii_lowercase().as_str() {
+        "sha256" => Ok(hex_digest::<Sha256>(bytes)),
+        "sha1" => Ok(hex_digest::<Sha1>(bytes)),
+        "md5" => Ok(format!("{:x}", md5::compute(bytes))),
+        other => anyhow::bail!("Unsupported checksum type {:?} in repomd.xml", other),
+    }
+}
+
+fn hex_digest<D: Digest>(bytes: &[u8]) -> String {
+    let mut hasher = D::new();
+    hasher.update(bytes);
+    hex::encode(hasher.finalize())
+}
+
+/// Verifies `primary_bytes` against the checksum repomd.xml advertised for it, decompresses
+/// it, and parses the result into a package list. Shared by [`Repo::fetch`] and
+/// [`Repo::fetch_async`] so the two download paths can't drift apart.
+fn parse_primary_data(
+    primary_url: &str,
+    checksum_type: &str,
+    checksum_value: &str,
+    primary_bytes: &[u8],
+) -> Result<Vec<Package>> {
+    let digest = checksum_digest(checksum_type, primary_bytes)
+        .with_context(|| format!("Failed to verify checksum of {:?}", primary_url))?;
+    anyhow::ensure!(
+        digest.eq_ignore_ascii_case(checksum_value),
+        "Checksum mismatch for {:?}: repomd.xml says {}, downloaded data hashes to {}",
+        primary_url,
+        checksum_value,
+        digest
+    );
+
+    let compression = Compression::detect(primary_url, primary_bytes);
+    let primary_xml = decompress(compression, primary_bytes)
+        .with_context(|| format!("Failed to decompress {:?}", primary_url))?;
+    let repo: Repo =
+        quick_xml::de::from_str(&primary_xml).with_context(|| "Failed to parse primary.xml")?;
+    Ok(repo.packages)
+}
+
 impl Repo {
-    fn from_baseurl(repo_url: String) -> Result<Repo> {
+    pub fn from_baseurl(repo_name: &str, repo_url: String) -> Result<Repo> {
+        Repo::from_baseurls(repo_name, vec![repo_url])
+    }
+
+    // Try each mirror in order, failing over to the next on error, until one yields a repo.
+    pub fn from_baseurls(repo_name: &str, mirrors: Vec<String>) -> Result<Repo> {
+        anyhow::ensure!(!mirrors.is_empty(), "No mirrors available for repo {:?}", repo_name);
+        let mut last_err = None;
+        for mirror_url in mirrors {
+            match Repo::fetch(repo_name, mirror_url) {
+                Ok(repo) => return Ok(repo),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    fn fetch(repo_name: &str, repo_url: String) -> Result<Repo> {
         // Get repomd.xml from the repo.
         let repomd_url = repo_url.clone() + "repodata/repomd.xml";
         let repomd_xml = reqwest::blocking::get(&repomd_url)
@@ -100,29 +224,163 @@ impl Repo {
         // Deserialize repomd.xml into a structure using serde.
         let repomd: Repomd =
             quick_xml::de::from_str(&repomd_xml).with_context(|| "Failed to parse repomd.xml")?;
-        // Get the url of primary.xml.gz, download and decompress it.
-        let mut primary_gz_url = repo_url.clone();
-        for data in &repomd.datas {
-            if data.r#type == "primary" {
-                primary_gz_url = primary_gz_url + &data.location.href;
-                break;
-            }
+        let primary_data = repomd
+            .datas
+            .iter()
+            .find(|data| data.r#type == "primary")
+            .with_context(|| "repomd.xml has no primary data entry")?;
+        let checksum = &primary_data.checksum.value;
+
+        // Skip the download/parse entirely if this repo's primary data is already cached.
+        let cache = cache::open()?;
+        if let Some(packages) = cache::get(&cache, repo_name, checksum)? {
+            return Ok(Repo {
+                packages,
+                name: repo_name.to_string(),
+            });
         }
-        let primary_gz_bytes: Result<Vec<_>, _> = reqwest::blocking::get(&primary_gz_url)
-            .with_context(|| format!("Failed to connect to {:?}", &primary_gz_url))?
+
+        // Get the url of the (possibly compressed) primary data file, and download it.
+        let primary_url = repo_url + &primary_data.location.href;
+        let primary_bytes: Result<Vec<_>, _> = reqwest::blocking::get(&primary_url)
+            .with_context(|| format!("Failed to connect to {:?}", &primary_url))?
             .bytes()?
             .bytes()
             .collect();
-        let primary_gz_bytes = primary_gz_bytes.unwrap();
-        let mut primary_gz = GzDecoder::new(&primary_gz_bytes[..]);
-        let mut primary_xml = String::new();
-        primary_gz.read_to_string(&mut primary_xml)?;
-        quick_xml::de::from_str(&primary_xml).with_context(|| "Failed to parse primary.xml")
+        let primary_bytes = primary_bytes.unwrap();
+
+        let packages = parse_primary_data(
+            &primary_url,
+            &primary_data.checksum.r#type,
+            &primary_data.checksum.value,
+            &primary_bytes,
+        )?;
+
+        cache::put(&cache, repo_name, checksum, &packages)?;
+
+        Ok(Repo {
+            packages,
+            name: repo_name.to_string(),
+        })
+    }
+
+    /// Async counterpart of [`Repo::fetch`], used so multiple repos can be downloaded
+    /// concurrently instead of one at a time. Takes `repo_name` by value (unlike the sync
+    /// `fetch`) so the future it returns doesn't borrow from its caller's stack frame,
+    /// which matters once it's driven concurrently by `join_all`.
+    async fn fetch_async(repo_name: String, repo_url: String) -> Result<Repo> {
+        // Get repomd.xml from the repo.
+        let repomd_url = repo_url.clone() + "repodata/repomd.xml";
+        let repomd_xml = reqwest::get(&repomd_url)
+            .await
+            .with_context(|| format!("Failed to connect to {:?}", &repomd_url))?
+            .text()
+            .await?;
+        // Deserialize repomd.xml into a structure using serde.
+        let repomd: Repomd =
+            quick_xml::de::from_str(&repomd_xml).with_context(|| "Failed to parse repomd.xml")?;
+        let primary_data = repomd
+            .datas
+            .iter()
+            .find(|data| data.r#type == "primary")
+            .with_context(|| "repomd.xml has no primary data entry")?;
+        let checksum = primary_data.checksum.value.clone();
+
+        // Skip the download/parse entirely if this repo's primary data is already cached.
+        // The cache is a blocking rusqlite connection, so it runs on the blocking pool
+        // rather than stalling this task's async worker thread.
+        let repo_name_owned = repo_name.clone();
+        let checksum_for_lookup = checksum.clone();
+        let cached = task::spawn_blocking(move || -> Result<Option<Vec<Package>>> {
+            let cache = cache::open()?;
+            cache::get(&cache, &repo_name_owned, &checksum_for_lookup)
+        })
+        .await
+        .with_context(|| "Cache lookup task panicked")??;
+        if let Some(packages) = cached {
+            return Ok(Repo {
+                packages,
+                name: repo_name,
+            });
+        }
+
+        // Get the url of the (possibly compressed) primary data file, and download it.
+        let primary_url = repo_url + &primary_data.location.href;
+        let primary_bytes: Result<Vec<_>, _> = reqwest::get(&primary_url)
+            .await
+            .with_context(|| format!("Failed to connect to {:?}", &primary_url))?
+            .bytes()
+            .await?
+            .bytes()
+            .collect();
+        let primary_bytes = primary_bytes.unwrap();
+
+        let packages = parse_primary_data(
+            &primary_url,
+            &primary_data.checksum.r#type,
+            &primary_data.checksum.value,
+            &primary_bytes,
+        )?;
+
+        let repo_name_owned = repo_name.clone();
+        let packages = task::spawn_blocking(move || -> Result<Vec<Package>> {
+            let cache = cache::open()?;
+            cache::put(&cache, &repo_name_owned, &checksum, &packages)?;
+            Ok(packages)
+        })
+        .await
+        .with_context(|| "Cache write task panicked")??;
+
+        Ok(Repo {
+            packages,
+            name: repo_name,
+        })
+    }
+
+    // Async counterpart of `from_baseurls`: try each mirror in order, failing over to the
+    // next on error, until one yields a repo.
+    async fn from_baseurls_async(repo_name: String, mirrors: Vec<String>) -> Result<Repo> {
+        anyhow::ensure!(
+            !mirrors.is_empty(),
+            "No mirrors available for repo {:?}",
+            repo_name
+        );
+        let mut last_err = None;
+        for mirror_url in mirrors {
+            match Repo::fetch_async(repo_name.clone(), mirror_url).await {
+                Ok(repo) => return Ok(repo),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// Fetches several repos concurrently, one `repomd.xml`/`primary` download chain per
+    /// repo running at once rather than sequentially, cutting cold-start latency when
+    /// multiple large repos are configured. Each `(repo_name, mirrors)` pair is resolved
+    /// independently, so one repo failing doesn't stop the others from completing.
+    pub async fn from_many_baseurls(repos: Vec<(String, Vec<String>)>) -> Vec<Result<Repo>> {
+        let fetches = repos
+            .into_iter()
+            .map(|(repo_name, mirrors)| Repo::from_baseurls_async(repo_name, mirrors));
+        futures::future::join_all(fetches).await
+    }
+
+    /// Merges the package sets of several repos into a single unified candidate pool, so a
+    /// requirement pulled in by a package from one repo can be satisfied by a `provides`
+    /// from another, matching how multi-repo RPM setups resolve dependencies.
+    pub fn merge(repos: Vec<Repo>) -> Repo {
+        let names: Vec<String> = repos.iter().map(|repo| repo.name.clone()).collect();
+        let packages = repos.into_iter().flat_map(|repo| repo.packages).collect();
+        Repo {
+            packages,
+            name: names.join(","),
+        }
     }
 
     // Read the .repo config file at path,
     // then return a vector of repos in the file.
-    fn from_file(path: &Path) -> Result<Vec<Repo>> {
+    pub fn from_file(path: &Path) -> Result<Vec<Repo>> {
         let mut repos: Vec<Repo> = Vec::new();
         // Parse .repo config file into a map.
         let mut config = configparser::ini::Ini::new_cs();
@@ -131,6 +389,8 @@ impl Repo {
         for (_, kvs) in map {
             let mut repo_name = String::new();
             let mut repo_baseurl = String::new();
+            let mut mirrorlist = String::new();
+            let mut metalink = String::new();
             for (key, value) in kvs {
                 match key.trim() {
                     "name" => {
@@ -140,52 +400,170 @@ impl Repo {
                         repo_baseurl = value.unwrap();
                     }
                     "mirrorlist" => {
-                        // To be done...
+                        mirrorlist = value.unwrap();
+                    }
+                    "metalink" => {
+                        metalink = value.unwrap();
                     }
                     _ => (),
                 }
             }
-            // Replace yum variables.
-            //
-            // $basearch refers to the base architecture of the system.
-            // For example, i686 machines have a base architecture of i386,
-            // and AMD64 and Intel 64 machines have a base architecture of x86_64.
-            if repo_baseurl.contains("$basearch") {
-                let mut basearch = String::from_utf8(Command::new("arch").output()?.stdout)?;
-                if basearch == "i686" {
-                    basearch = String::from("i386");
-                }
-                repo_baseurl = repo_baseurl.replace("$basearch", &basearch);
-            }
-            // $arch refers to the system's CPU architecture.
-            if repo_baseurl.contains("$arch") {
-                let arch = String::from_utf8(Command::new("arch").output()?.stdout)?;
-                repo_baseurl = repo_baseurl.replace("$arch", &arch);
-            }
-            // $releasever refers to the release version of the system.
-            // Yum obtains the value of $releasever from the distroverpkg=value line in the /etc/yum.conf configuration file.
-            // If there is no such line in /etc/yum.conf,
-            // then yum infers the correct value by deriving the version number from the system-release package.
-            if repo_baseurl.contains("$releasever") {
-                let release = String::from_utf8(
-                    Command::new("rpm")
-                        .args(["-q", "openEuler-release"])
-                        .output()?
-                        .stdout,
+
+            let mirrors = resolve_mirrors(
+                (!repo_baseurl.is_empty()).then_some(repo_baseurl.as_str()),
+                (!mirrorlist.is_empty()).then_some(mirrorlist.as_str()),
+                (!metalink.is_empty()).then_some(metalink.as_str()),
+            )
+            .with_context(|| {
+                format!(
+                    "Repo {:?} has no usable mirrors",
+                    repo_name
                 )
-                .with_context(|| "System-release package not found")?;
-                let release: Vec<&str> = release.split("-").collect();
-                let releasever = release[2];
-                repo_baseurl = repo_baseurl.replace("$releasever", releasever);
-            }
-            let mut repo = Repo::from_baseurl(repo_baseurl)?;
-            repo.name = repo_name;
-            repos.push(repo);
+            })?;
+            repos.push(Repo::from_baseurls(&repo_name, mirrors)?);
         }
         Ok(repos)
     }
 }
 
+/// Resolves a repo's `baseurl`/`mirrorlist`/`metalink` (at most one of which is expected
+/// to be set, checked in that order) into the ordered list of baseurls
+/// [`Repo::from_baseurls`] should try, applying yum variable substitution along the way.
+/// Shared by [`Repo::from_file`] and [`crate::config::Config::get_repos`] so a repo
+/// defined either way gets the same mirror resolution.
+pub(crate) fn resolve_mirrors(
+    baseurl: Option<&str>,
+    mirrorlist: Option<&str>,
+    metalink: Option<&str>,
+) -> Result<Vec<String>> {
+    if let Some(baseurl) = baseurl {
+        return Ok(vec![substitute_yum_vars(baseurl.to_string())?]);
+    }
+    if let Some(mirrorlist) = mirrorlist {
+        let mirrorlist_url = substitute_yum_vars(mirrorlist.to_string())?;
+        return mirrors_from_mirrorlist(&mirrorlist_url);
+    }
+    if let Some(metalink) = metalink {
+        let metalink_url = substitute_yum_vars(metalink.to_string())?;
+        return mirrors_from_metalink(&metalink_url);
+    }
+    anyhow::bail!("none of baseurl, mirrorlist or metalink is set");
+}
+
+// Replace yum variables.
+//
+// $basearch refers to the base architecture of the system.
+// For example, i686 machines have a base architecture of i386,
+// and AMD64 and Intel 64 machines have a base architecture of x86_64.
+//
+// $arch refers to the system's CPU architecture.
+//
+// $releasever refers to the release version of the system.
+// Yum obtains the value of $releasever from the distroverpkg=value line in the /etc/yum.conf configuration file.
+// If there is no such line in /etc/yum.conf,
+// then yum infers the correct value by deriving the version number from the system-release package.
+fn substitute_yum_vars(mut url: String) -> Result<String> {
+    if url.contains("$basearch") {
+        let mut basearch = String::from_utf8(Command::new("arch").output()?.stdout)?
+            .trim()
+            .to_string();
+        if basearch == "i686" {
+            basearch = String::from("i386");
+        }
+        url = url.replace("$basearch", &basearch);
+    }
+    if url.contains("$arch") {
+        let arch = String::from_utf8(Command::new("arch").output()?.stdout)?
+            .trim()
+            .to_string();
+        url = url.replace("$arch", &arch);
+    }
+    if url.contains("$releasever") {
+        let release = String::from_utf8(
+            Command::new("rpm")
+                .args(["-q", "openEuler-release"])
+                .output()?
+                .stdout,
+        )
+        .with_context(|| "System-release package not found")?;
+        let release: Vec<&str> = release.split("-").collect();
+        let releasever = release[2];
+        url = url.replace("$releasever", releasever);
+    }
+    Ok(url)
+}
+
+// A mirrorlist is a plain-text file with one mirror baseurl per line.
+fn mirrors_from_mirrorlist(mirrorlist_url: &str) -> Result<Vec<String>> {
+    let body = reqwest::blocking::get(mirrorlist_url)
+        .with_context(|| format!("Failed to connect to {:?}", mirrorlist_url))?
+        .text()?;
+    Ok(body
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Metalink {
+    files: MetalinkFiles,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MetalinkFiles {
+    file: MetalinkFile,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MetalinkFile {
+    resources: MetalinkResources,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MetalinkResources {
+    #[serde(rename = "url")]
+    urls: Vec<MetalinkUrl>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MetalinkUrl {
+    protocol: String,
+    #[serde(rename = "$value")]
+    value: String,
+}
+
+// Metalink <url> entries point directly at repodata/repomd.xml rather than at the repo
+// root, so strip that suffix to recover a baseurl comparable to a mirrorlist entry.
+fn baseurl_from_metalink_url(url: &str) -> String {
+    url.trim_end_matches("repodata/repomd.xml").to_string()
+}
+
+fn protocol_rank(protocol: &str) -> u8 {
+    match protocol.to_ascii_lowercase().as_str() {
+        "https" => 0,
+        "http" => 1,
+        _ => 2,
+    }
+}
+
+// A metalink is an XML document listing mirrors (and checksums rust-solv doesn't need,
+// since it verifies against repomd.xml itself), preferring https mirrors first.
+fn mirrors_from_metalink(metalink_url: &str) -> Result<Vec<String>> {
+    let body = reqwest::blocking::get(metalink_url)
+        .with_context(|| format!("Failed to connect to {:?}", metalink_url))?
+        .text()?;
+    let metalink: Metalink =
+        quick_xml::de::from_str(&body).with_context(|| "Failed to parse metalink")?;
+    let mut urls = metalink.files.file.resources.urls;
+    urls.sort_by_key(|url| protocol_rank(&url.protocol));
+    Ok(urls
+        .into_iter()
+        .map(|url| baseurl_from_metalink_url(&url.value))
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,7 +571,7 @@ mod tests {
     #[test]
     fn test_parse_primary_xml() -> Result<()> {
         let repo_url = String::from("https://repo.openeuler.org/openEuler-22.03-LTS/OS/x86_64/");
-        let repo: Repo = Repo::from_baseurl(repo_url)?;
+        let repo: Repo = Repo::from_baseurl("openEuler-22.03-LTS", repo_url)?;
         println!("{:?}", repo.packages);
         Ok(())
     }
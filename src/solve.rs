@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+
+use crate::evr::{evr_compare, evr_satisfies, Evr};
+use crate::repo::{Entry, Package, Repo};
+
+/// Why [`resolve`] could not produce an install set.
+#[derive(Debug)]
+pub enum ResolveError {
+    /// No candidate in the repo matches this requirement's name and, where the requirement
+    /// carries a version flag, its EVR.
+    Unsatisfied {
+        /// The chain of package names (root first) that pulled in the unmet requirement,
+        /// followed by the requirement's own name.
+        chain: Vec<String>,
+    },
+    /// A candidate able to satisfy the requirement conflicts with (or obsoletes, or is
+    /// obsoleted by) an already-selected package, and no other candidate led to an install set.
+    Conflict {
+        /// The chain of package names (root first) that pulled in the requirement,
+        /// followed by the requirement's own name.
+        chain: Vec<String>,
+        /// The already-selected package the candidate conflicted or obsoleted with; where
+        /// candidates hit different packages, the earliest-selected one.
+        with: String,
+    },
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::Unsatisfied { chain } => {
+                write!(f, "unsatisfied dependency: {}", chain.join(" -> "))
+            }
+            ResolveError::Conflict { chain, with } => write!(
+                f,
+                "conflicting dependency: {} (conflicts with already-selected {})",
+                chain.join(" -> "),
+                with
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// An unmet `Requires` entry together with the chain of packages that pulled it in.
+#[derive(Clone)]
+struct Pending {
+    entry: Entry,
+    chain: Vec<String>,
+}
+
+/// A search failure, tagged with the decision level (index into the selection, i.e. the
+/// position of a prior choice in the install set) that should be retried to fix it.
+struct Blame {
+    level: usize,
+    error: ResolveError,
+}
+
+/// Resolves `package_names` against `repo`, returning the ordered install set, or a
+/// [`ResolveError`] describing why no install set exists.
+///
+/// This is a backtracking search over the repo's `Provides`/`Requires`/`Conflicts`/
+/// `Obsoletes` graph: a worklist of unmet requirements is processed one at a time, each
+/// candidate provider is tried highest-EVR-first, and a `Conflicts`/`Obsoletes` hit jumps
+/// the search back to the decision that selected the offending package rather than simply
+/// undoing the most recent choice.
+pub fn resolve<'a>(
+    repo: &'a Repo,
+    package_names: &[String],
+) -> Result<Vec<&'a Package>, ResolveError> {
+    let worklist: VecDeque<Pending> = package_names
+        .iter()
+        .map(|name| Pending {
+            entry: root_entry(name),
+            chain: Vec::new(),
+        })
+        .collect();
+    let mut selected: Vec<&'a Package> = Vec::new();
+    search(repo, worklist, &mut selected).map_err(|blame| blame.error)
+}
+
+fn search<'a>(
+    repo: &'a Repo,
+    mut worklist: VecDeque<Pending>,
+    selected: &mut Vec<&'a Package>,
+) -> Result<Vec<&'a Package>, Blame> {
+    let Some(pending) = worklist.pop_front() else {
+        return Ok(selected.clone());
+    };
+
+    if selected
+        .iter()
+        .any(|package| package_matches_entry(package, &pending.entry))
+    {
+        return search(repo, worklist, selected);
+    }
+
+    let depth = selected.len();
+    let mut candidates = candidates_for(repo, &pending.entry);
+    candidates.sort_by(|a, b| evr_compare(&package_evr(b), &package_evr(a)));
+
+    if candidates.is_empty() {
+        return Err(Blame {
+            level: depth,
+            error: ResolveError::Unsatisfied {
+                chain: full_chain(&pending),
+            },
+        });
+    }
+
+    let mut best_blame: Option<Blame> = None;
+    for candidate in candidates {
+        if let Some(conflict_level) = conflicts_with_selected(candidate, selected) {
+            let blame = Blame {
+                level: conflict_level,
+                error: ResolveError::Conflict {
+                    chain: full_chain(&pending),
+                    with: selected[conflict_level].name.clone(),
+                },
+            };
+            best_blame = keep_shallower(best_blame, blame);
+            continue;
+        }
+
+        selected.push(candidate);
+        let mut next_worklist = worklist.clone();
+        if let Some(requires) = &candidate.format.requires {
+            let mut chain = pending.chain.clone();
+            chain.push(candidate.name.clone());
+            next_worklist.extend(requires.entries.iter().map(|entry| Pending {
+                entry: entry.clone(),
+                chain: chain.clone(),
+            }));
+        }
+
+        match search(repo, next_worklist, selected) {
+            Ok(install_set) => return Ok(install_set),
+            Err(blame) => {
+                selected.pop();
+                if blame.level < depth {
+                    // The fix lies at an ancestor decision; trying our remaining
+                    // candidates here can't help, so jump straight back to it.
+                    return Err(blame);
+                }
+                best_blame = keep_shallower(best_blame, blame);
+            }
+        }
+    }
+
+    Err(best_blame.unwrap_or_else(|| Blame {
+        level: depth,
+        error: ResolveError::Unsatisfied {
+            chain: full_chain(&pending),
+        },
+    }))
+}
+
+fn keep_shallower(current: Option<Blame>, candidate: Blame) -> Option<Blame> {
+    match current {
+        Some(current) if current.level <= candidate.level => Some(current),
+        _ => Some(candidate),
+    }
+}
+
+fn full_chain(pending: &Pending) -> Vec<String> {
+    let mut chain = pending.chain.clone();
+    chain.push(pending.entry.name.clone());
+    chain
+}
+
+fn root_entry(name: &str) -> Entry {
+    Entry {
+        name: name.to_string(),
+        flags: None,
+        epoch: None,
+        ver: None,
+        rel: None,
+    }
+}
+
+fn candidates_for<'a>(repo: &'a Repo, entry: &Entry) -> Vec<&'a Package> {
+    repo.packages
+        .iter()
+        .filter(|package| package_matches_entry(package, entry))
+        .collect()
+}
+
+fn conflicts_with_selected(candidate: &Package, selected: &[&Package]) -> Option<usize> {
+    selected.iter().position(|&existing| {
+        package_conflicts(candidate, existing)
+            || package_conflicts(existing, candidate)
+            || package_obsoletes(candidate, existing)
+            || package_obsoletes(existing, candidate)
+    })
+}
+
+fn package_conflicts(package: &Package, other: &Package) -> bool {
+    package
+        .format
+        .conflicts
+        .as_ref()
+        .is_some_and(|conflicts| conflicts.entries.iter().any(|entry| package_matches_entry(other, entry)))
+}
+
+fn package_obsoletes(package: &Package, other: &Package) -> bool {
+    if package.name == other.name {
+        return false;
+    }
+    package
+        .format
+        .obsoletes
+        .as_ref()
+        .is_some_and(|obsoletes| obsoletes.entries.iter().any(|entry| package_matches_entry(other, entry)))
+}
+
+fn package_matches_entry(package: &Package, entry: &Entry) -> bool {
+    if package.name == entry.name && entry_satisfied_by(entry, &package_evr(package)) {
+        return true;
+    }
+    package.format.provides.as_ref().is_some_and(|provides| {
+        provides
+            .entries
+            .iter()
+            .any(|provided| provided.name == entry.name && entry_satisfied_by(entry, &entry_evr(provided)))
+    })
+}
+
+fn entry_satisfied_by(entry: &Entry, provided_evr: &Evr<'_>) -> bool {
+    match &entry.flags {
+        Some(flag) => evr_satisfies(provided_evr, flag, &entry_evr(entry)),
+        None => true,
+    }
+}
+
+fn entry_evr(entry: &Entry) -> Evr<'_> {
+    Evr {
+        epoch: entry.epoch.as_deref().unwrap_or("0"),
+        ver: entry.ver.as_deref().unwrap_or(""),
+        rel: entry.rel.as_deref().unwrap_or(""),
+    }
+}
+
+fn package_evr(package: &Package) -> Evr<'_> {
+    Evr {
+        epoch: &package.version.epoch,
+        ver: &package.version.ver,
+        rel: &package.version.rel,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::{Conflicts, Format, Provides, Requires, Version};
+
+    fn entry(name: &str, flags: Option<&str>, ver: Option<&str>) -> Entry {
+        Entry {
+            name: name.to_string(),
+            flags: flags.map(String::from),
+            epoch: None,
+            ver: ver.map(String::from),
+            rel: None,
+        }
+    }
+
+    fn package(
+        name: &str,
+        ver: &str,
+        requires: Vec<Entry>,
+        provides: Vec<Entry>,
+        conflicts: Vec<Entry>,
+    ) -> Package {
+        Package {
+            r#type: "rpm".to_string(),
+            name: name.to_string(),
+            version: Version {
+                epoch: "0".to_string(),
+                ver: ver.to_string(),
+                rel: "1".to_string(),
+            },
+            format: Format {
+                requires: (!requires.is_empty()).then_some(Requires { entries: requires }),
+                provides: (!provides.is_empty()).then_some(Provides { entries: provides }),
+                conflicts: (!conflicts.is_empty()).then_some(Conflicts { entries: conflicts }),
+                obsoletes: None,
+            },
+        }
+    }
+
+    fn repo(packages: Vec<Package>) -> Repo {
+        Repo {
+            packages,
+            name: "test".to_string(),
+        }
+    }
+
+    fn names<'a>(packages: &'a [&'a Package]) -> Vec<&'a str> {
+        packages.iter().map(|package| package.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_resolve_satisfied_chain() {
+        let repo = repo(vec![
+            package("app", "1.0", vec![entry("lib", None, None)], vec![], vec![]),
+            package("lib", "1.0", vec![], vec![], vec![]),
+        ]);
+        let install_set = resolve(&repo, &["app".to_string()]).unwrap();
+        assert_eq!(names(&install_set), vec!["app", "lib"]);
+    }
+
+    #[test]
+    fn test_resolve_unsatisfied() {
+        let repo = repo(vec![package(
+            "app",
+            "1.0",
+            vec![entry("missing", None, None)],
+            vec![],
+            vec![],
+        )]);
+        let err = resolve(&repo, &["app".to_string()]).unwrap_err();
+        match err {
+            ResolveError::Unsatisfied { chain } => assert_eq!(chain, vec!["app", "missing"]),
+            other => panic!("expected Unsatisfied, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resolve_backtracks_on_conflict() {
+        let repo = repo(vec![
+            package("base", "1.0", vec![], vec![], vec![]),
+            package(
+                "app",
+                "1.0",
+                vec![entry("feature", None, None)],
+                vec![],
+                vec![],
+            ),
+            package(
+                "feature-new",
+                "1.0",
+                vec![],
+                vec![entry("feature", None, None)],
+                vec![],
+            ),
+            package(
+                "feature-old",
+                "2.0",
+                vec![],
+                vec![entry("feature", None, None)],
+                vec![entry("base", None, None)],
+            ),
+        ]);
+        // "feature-old" has the higher EVR and would normally be tried first, but it
+        // conflicts with the already-selected "base", so the search should backtrack to
+        // "feature-new" instead of failing outright.
+        let install_set = resolve(&repo, &["base".to_string(), "app".to_string()]).unwrap();
+        assert_eq!(names(&install_set), vec!["base", "app", "feature-new"]);
+    }
+
+    #[test]
+    fn test_resolve_conflict_with_no_alternative() {
+        let repo = repo(vec![
+            package("base", "1.0", vec![], vec![], vec![]),
+            package(
+                "app",
+                "1.0",
+                vec![entry("feature", None, None)],
+                vec![],
+                vec![],
+            ),
+            package(
+                "feature-old",
+                "2.0",
+                vec![],
+                vec![entry("feature", None, None)],
+                vec![entry("base", None, None)],
+            ),
+        ]);
+        let err = resolve(&repo, &["base".to_string(), "app".to_string()]).unwrap_err();
+        match err {
+            ResolveError::Conflict { chain, with } => {
+                assert_eq!(chain, vec!["app", "feature"]);
+                assert_eq!(with, "base");
+            }
+            other => panic!("expected Conflict, got {:?}", other),
+        }
+    }
+}